@@ -1,8 +1,12 @@
 use std::fs::File;
 use std::io::{self, BufRead};
-use std::collections::{HashSet, HashMap, VecDeque};
+use std::cmp::Reverse;
+use std::collections::{HashSet, HashMap, VecDeque, BinaryHeap};
 mod stats;
-use stats::{bfs, mean_distance, std_dev, max_distance};
+use stats::{mean_distance, std_dev, max_distance};
+
+/// Where the cached all-pairs hop matrix is stored between runs.
+const DISTANCE_MATRIX_CACHE: &str = "distance_matrix.bin";
 
 /// Trip categories: Business or Personal
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
@@ -11,52 +15,115 @@ enum Category {
     Personal,
 }
 
-/// Read (start, stop, category) from CSV or return test cycle
-fn read_file(path: &str) -> Vec<(String, String, Category)> {
+/// A single parsed ride record from the Uber dataset.
+#[derive(Clone, Debug, PartialEq)]
+struct Ride {
+    start: String,
+    stop: String,
+    category: Category,
+    miles: f64,
+    start_date: String,
+    purpose: String,
+}
+
+/// Split one CSV record into fields, honoring RFC-4180 double-quoting so a
+/// comma or embedded quote inside `"..."` isn't mistaken for a delimiter.
+fn parse_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            if c == '"' {
+                if chars.peek() == Some(&'"') {
+                    field.push('"');
+                    chars.next();
+                } else {
+                    in_quotes = false;
+                }
+            } else {
+                field.push(c);
+            }
+        } else {
+            match c {
+                '"' => in_quotes = true,
+                ',' => fields.push(std::mem::take(&mut field)),
+                _ => field.push(c),
+            }
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+/// Read rides from CSV, looking columns up by header name rather than a
+/// fixed position, or return the test cycle for the `test.txt` shortcut.
+fn read_file(path: &str) -> Vec<Ride> {
     if path == "test.txt" {
         return vec![
-            ("A".into(), "B".into(), Category::Personal),
-            ("B".into(), "C".into(), Category::Personal),
-            ("C".into(), "A".into(), Category::Personal),
+            Ride { start: "A".into(), stop: "B".into(), category: Category::Personal, miles: 1.0, start_date: String::new(), purpose: String::new() },
+            Ride { start: "B".into(), stop: "C".into(), category: Category::Personal, miles: 1.0, start_date: String::new(), purpose: String::new() },
+            Ride { start: "C".into(), stop: "A".into(), category: Category::Personal, miles: 1.0, start_date: String::new(), purpose: String::new() },
         ];
     }
     let file = File::open(path).expect("Could not open file");
+    let mut lines = io::BufReader::new(file).lines();
+
+    let header_line = lines.next().expect("Empty file").expect("Error reading header");
+    let header = parse_csv_line(&header_line);
+    let col = |name: &str| header.iter().position(|h| h.trim() == name);
+    let start_date_i = col("START_DATE*");
+    let category_i = col("CATEGORY*");
+    let start_i = col("START*");
+    let stop_i = col("STOP*");
+    let miles_i = col("MILES*");
+    let purpose_i = col("PURPOSE*");
+
     let mut rides = Vec::new();
-    let lines = io::BufReader::new(file).lines();
     for line in lines {
         let s = line.expect("Error reading line");
-        let parts: Vec<&str> = s.trim().split(',').collect();
-        // Expecting at least: CATEGORY, START, STOP
-        if parts.len() >= 5 {
-            let cat = if parts[2] == "Business" {
-                Category::Business
-            } else {
-                Category::Personal
-            };
-            rides.push((
-                parts[3].to_string(),  // START
-                parts[4].to_string(),  // STOP
-                cat,
-            ));
+        if s.trim().is_empty() {
+            continue;
         }
+        let fields = parse_csv_line(&s);
+        let get = |i: Option<usize>| i.and_then(|i| fields.get(i)).map(|v| v.trim().to_string()).unwrap_or_default();
+
+        let start = get(start_i);
+        let stop = get(stop_i);
+        let category = if get(category_i) == "Business" {
+            Category::Business
+        } else {
+            Category::Personal
+        };
+        let miles = get(miles_i).parse().unwrap_or(0.0);
+
+        rides.push(Ride {
+            start,
+            stop,
+            category,
+            miles,
+            start_date: get(start_date_i),
+            purpose: get(purpose_i),
+        });
     }
 
     rides
 }
 
 /// Collecting unique nodes
-fn unique_nodes(rides: &[(String, String, Category)]) -> HashSet<String> {
+fn unique_nodes(rides: &[Ride]) -> HashSet<String> {
     let mut set = HashSet::new();
-    for (s, d, _) in rides {
-        set.insert(s.clone());
-        set.insert(d.clone());
+    for r in rides {
+        set.insert(r.start.clone());
+        set.insert(r.stop.clone());
     }
     set
 }
 
 /// Build adjacency list and return location index map
 fn adjacency_list(
-    rides: &[(String, String, Category)],
+    rides: &[Ride],
     nodes: &HashSet<String>
 ) -> (Vec<Vec<usize>>, Vec<String>) {
     let mut locations: Vec<String> = nodes.iter().cloned().collect();
@@ -66,8 +133,8 @@ fn adjacency_list(
         index.insert(name.clone(), i);
     }
     let mut adjacency = vec![Vec::new(); locations.len()];
-    for (s, d, _) in rides {
-        if let (Some(&u), Some(&v)) = (index.get(s), index.get(d)) {
+    for r in rides {
+        if let (Some(&u), Some(&v)) = (index.get(&r.start), index.get(&r.stop)) {
             adjacency[u].push(v);
         }
     }
@@ -76,12 +143,12 @@ fn adjacency_list(
 
 /// Top-N frequent direct routes
 fn most_frequent_pairs(
-    rides: &[(String, String, Category)],
+    rides: &[Ride],
     most_frequent: usize
 ) -> Vec<((String, String), usize)> {
     let mut count = HashMap::new();
-    for (start, end, _category) in rides {
-        let key = (start.clone(), end.clone());
+    for r in rides {
+        let key = (r.start.clone(), r.stop.clone());
         *count.entry(key).or_insert(0) += 1;
     }
     let mut pairs_counts: Vec<_> = count.into_iter().collect();
@@ -99,13 +166,13 @@ fn most_frequent_pairs(
 }
 
 /// Popular hubs by category
-fn popular_hubs(rides: &[(String, String, Category)]) -> (String, String) {
+fn popular_hubs(rides: &[Ride]) -> (String, String) {
     let mut personal_counts = HashMap::new();
     let mut business_counts = HashMap::new();
-    for (start, end, cat) in rides {
-        let map = if *cat == Category::Personal { &mut personal_counts } else { &mut business_counts };
-        *map.entry(start.clone()).or_insert(0) += 1;
-        *map.entry(end.clone()).or_insert(0) += 1;
+    for r in rides {
+        let map = if r.category == Category::Personal { &mut personal_counts } else { &mut business_counts };
+        *map.entry(r.start.clone()).or_insert(0) += 1;
+        *map.entry(r.stop.clone()).or_insert(0) += 1;
     }
     let highest_val = |map: &HashMap<String, usize>| {
         map.iter()
@@ -118,7 +185,7 @@ fn popular_hubs(rides: &[(String, String, Category)]) -> (String, String) {
 
 /// Shortest path by hops
 fn shortest_path(
-    adj: &Vec<Vec<usize>>,
+    adj: &[Vec<usize>],
     start: usize,
     end: usize
 ) -> Option<Vec<usize>> {
@@ -139,7 +206,88 @@ fn shortest_path(
             }
         }
     }
-    if distance[end].is_none() {
+    distance[end]?;
+    let mut path = Vec::new();
+    let mut current = end;
+    while let Some(p) = prev[current] {
+        path.push(current);
+        current = p;
+    }
+    path.push(start);
+    path.reverse();
+    Some(path)
+}
+
+/// Wraps an f64 so distances can sit in a `BinaryHeap`, which needs a total order.
+/// Ride costs are never NaN, so falling back to `Equal` never actually triggers.
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct OrderedFloat(f64);
+
+impl Eq for OrderedFloat {}
+
+impl PartialOrd for OrderedFloat {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OrderedFloat {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.partial_cmp(&other.0).unwrap_or(std::cmp::Ordering::Equal)
+    }
+}
+
+/// Build a weighted adjacency list where each edge costs `1.0 / ride_count`,
+/// so a heavily-traveled corridor is "cheaper" than a one-off trip.
+fn adjacency_list_weighted(
+    rides: &[Ride],
+    nodes: &HashSet<String>
+) -> (Vec<Vec<(usize, f64)>>, Vec<String>) {
+    let mut locations: Vec<String> = nodes.iter().cloned().collect();
+    locations.sort();
+    let mut index = HashMap::new();
+    for (i, name) in locations.iter().enumerate() {
+        index.insert(name.clone(), i);
+    }
+    let mut counts: HashMap<(usize, usize), usize> = HashMap::new();
+    for r in rides {
+        if let (Some(&u), Some(&v)) = (index.get(&r.start), index.get(&r.stop)) {
+            *counts.entry((u, v)).or_insert(0) += 1;
+        }
+    }
+    let mut adjacency = vec![Vec::new(); locations.len()];
+    for ((u, v), count) in counts {
+        adjacency[u].push((v, 1.0 / count as f64));
+    }
+    (adjacency, locations)
+}
+
+/// Weighted shortest path via Dijkstra over `adjacency_list_weighted`'s edge costs.
+fn shortest_path_weighted(
+    adj: &[Vec<(usize, f64)>],
+    start: usize,
+    end: usize
+) -> Option<(Vec<usize>, f64)> {
+    let mut prev = vec![None; adj.len()];
+    let mut distance = vec![f64::INFINITY; adj.len()];
+    let mut heap = BinaryHeap::new();
+    distance[start] = 0.0;
+    heap.push(Reverse((OrderedFloat(0.0), start)));
+
+    while let Some(Reverse((OrderedFloat(d), u))) = heap.pop() {
+        if u == end { break; }
+        if d > distance[u] { continue; } // stale entry, a cheaper one already won
+        for &(v, cost) in &adj[u] {
+            let next = d + cost;
+            if next < distance[v] {
+                distance[v] = next;
+                prev[v] = Some(u);
+                heap.push(Reverse((OrderedFloat(next), v)));
+            }
+        }
+    }
+
+    if distance[end].is_infinite() {
         return None;
     }
     let mut path = Vec::new();
@@ -150,16 +298,289 @@ fn shortest_path(
     }
     path.push(start);
     path.reverse();
-    Some(path)
+    Some((path, distance[end]))
+}
+
+/// Load per-location coordinates from a sidecar CSV (`name,lat,lon`, no header),
+/// aligned to `locations` so missing entries fall back to `None`.
+fn load_coords(path: &str, locations: &[String]) -> Vec<Option<(f64, f64)>> {
+    let mut by_name: HashMap<String, (f64, f64)> = HashMap::new();
+    if let Ok(file) = File::open(path) {
+        for line in io::BufReader::new(file).lines() {
+            let l = line.expect("Error reading line");
+            let parts = parse_csv_line(l.trim());
+            if parts.len() >= 3 {
+                if let (Ok(lat), Ok(lon)) = (parts[1].parse::<f64>(), parts[2].parse::<f64>()) {
+                    by_name.insert(parts[0].clone(), (lat, lon));
+                }
+            }
+        }
+    }
+    locations.iter().map(|name| by_name.get(name).copied()).collect()
+}
+
+/// Great-circle distance in km between two (lat, lon) points in degrees.
+fn haversine(a: (f64, f64), b: (f64, f64)) -> f64 {
+    const EARTH_RADIUS_KM: f64 = 6371.0;
+    let (lat1, lon1) = (a.0.to_radians(), a.1.to_radians());
+    let (lat2, lon2) = (b.0.to_radians(), b.1.to_radians());
+    let dlat = lat2 - lat1;
+    let dlon = lon2 - lon1;
+    let h = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * h.sqrt().atan2((1.0 - h).sqrt())
+}
+
+/// Build an adjacency list whose edge costs are the haversine distance (km)
+/// between each edge's endpoints, for use with `astar` — the heuristic is
+/// only admissible when edge costs are measured the same way. Edges where
+/// either endpoint lacks coordinates fall back to a single hop (cost 1.0).
+fn adjacency_list_haversine(
+    adj: &[Vec<usize>],
+    coords: &[Option<(f64, f64)>]
+) -> Vec<Vec<(usize, f64)>> {
+    adj.iter().enumerate().map(|(u, neighbors)| {
+        neighbors.iter().map(|&v| {
+            let cost = match (coords[u], coords[v]) {
+                (Some(a), Some(b)) => haversine(a, b),
+                _ => 1.0,
+            };
+            (v, cost)
+        }).collect()
+    }).collect()
+}
+
+/// A* search using haversine distance to the goal as the heuristic. Run this
+/// over `adjacency_list_haversine`'s edge costs; the heuristic is admissible
+/// only where those costs are real haversine distances, not the 1.0 hop used
+/// for edges touching a node without coordinates. Nodes with no coordinates
+/// get `h = 0`.
+fn astar(
+    adj: &[Vec<(usize, f64)>],
+    coords: &[Option<(f64, f64)>],
+    start: usize,
+    end: usize
+) -> Option<(Vec<usize>, f64)> {
+    let h = |n: usize| match (coords[n], coords[end]) {
+        (Some(a), Some(b)) => haversine(a, b),
+        _ => 0.0,
+    };
+
+    let mut prev = vec![None; adj.len()];
+    let mut g_score = vec![f64::INFINITY; adj.len()];
+    let mut heap = BinaryHeap::new();
+    g_score[start] = 0.0;
+    heap.push(Reverse((OrderedFloat(h(start)), start)));
+
+    while let Some(Reverse((_, u))) = heap.pop() {
+        if u == end { break; }
+        for &(v, cost) in &adj[u] {
+            let tentative = g_score[u] + cost;
+            if tentative < g_score[v] {
+                g_score[v] = tentative;
+                prev[v] = Some(u);
+                heap.push(Reverse((OrderedFloat(tentative + h(v)), v)));
+            }
+        }
+    }
+
+    if g_score[end].is_infinite() {
+        return None;
+    }
+    let mut path = Vec::new();
+    let mut current = end;
+    while let Some(p) = prev[current] {
+        path.push(current);
+        current = p;
+    }
+    path.push(start);
+    path.reverse();
+    Some((path, g_score[end]))
+}
+
+/// Above this many permutable stops, `best_tour` switches to a greedy
+/// nearest-neighbor fallback rather than enumerating `n!` orderings.
+const MAX_PERMUTABLE_STOPS: usize = 10;
+
+/// Heap's algorithm: invoke `visit` once per permutation of `items`, in place.
+fn permute(items: &mut [usize], visit: &mut dyn FnMut(&[usize])) {
+    let n = items.len();
+    visit(items);
+    if n == 0 {
+        return;
+    }
+    let mut c = vec![0usize; n];
+    let mut i = 0;
+    while i < n {
+        if c[i] < i {
+            if i % 2 == 0 {
+                items.swap(0, i);
+            } else {
+                items.swap(c[i], i);
+            }
+            visit(items);
+            c[i] += 1;
+            i = 0;
+        } else {
+            c[i] = 0;
+            i += 1;
+        }
+    }
+}
+
+/// Find the visiting order of `waypoints` (node indices) minimizing total hop
+/// count, optionally pinning the first and/or last stop. Tries every ordering
+/// of the free middle stops via `permute`, falling back to greedy
+/// nearest-neighbor once there are more than `MAX_PERMUTABLE_STOPS` of them.
+/// Returns the concatenated node path and its total hop cost.
+fn best_tour(
+    adj: &[Vec<usize>],
+    waypoints: &[usize],
+    keep_first: bool,
+    keep_last: bool
+) -> Option<(Vec<usize>, usize)> {
+    if waypoints.len() < 2 {
+        return Some((waypoints.to_vec(), 0));
+    }
+
+    let mut middle: Vec<usize> = waypoints.to_vec();
+    let first = if keep_first { Some(middle.remove(0)) } else { None };
+    let last = if keep_last && !middle.is_empty() { Some(middle.pop().unwrap()) } else { None };
+
+    let evaluate = |order: &[usize]| -> Option<(Vec<usize>, usize)> {
+        let mut full = Vec::new();
+        full.extend(first);
+        full.extend_from_slice(order);
+        full.extend(last);
+        if full.len() < 2 {
+            return Some((full, 0));
+        }
+        let mut total_path = vec![full[0]];
+        let mut total_cost = 0;
+        for pair in full.windows(2) {
+            let leg = shortest_path(adj, pair[0], pair[1])?;
+            total_cost += leg.len() - 1;
+            total_path.extend_from_slice(&leg[1..]);
+        }
+        Some((total_path, total_cost))
+    };
+
+    if middle.len() > MAX_PERMUTABLE_STOPS {
+        let mut remaining = middle.clone();
+        let mut order = Vec::new();
+        let mut current = match first {
+            Some(f) => f,
+            None => {
+                let start = remaining.remove(0);
+                order.push(start);
+                start
+            }
+        };
+        while !remaining.is_empty() {
+            let (idx, _) = remaining.iter().enumerate()
+                .filter_map(|(i, &n)| shortest_path(adj, current, n).map(|p| (i, p.len() - 1)))
+                .min_by_key(|&(_, cost)| cost)?;
+            current = remaining.remove(idx);
+            order.push(current);
+        }
+        return evaluate(&order);
+    }
+
+    let mut best: Option<(Vec<usize>, usize)> = None;
+    permute(&mut middle, &mut |order| {
+        if let Some((path, cost)) = evaluate(order) {
+            if best.as_ref().is_none_or(|(_, best_cost)| cost < *best_cost) {
+                best = Some((path, cost));
+            }
+        }
+    });
+    best
+}
+
+/// Search algorithm selected via `--mode`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Mode {
+    Bfs,
+    Dijkstra,
+    Astar,
+}
+
+impl std::str::FromStr for Mode {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "bfs" => Ok(Mode::Bfs),
+            "dijkstra" => Ok(Mode::Dijkstra),
+            "astar" => Ok(Mode::Astar),
+            other => Err(format!("unknown --mode '{}': expected bfs, dijkstra, or astar", other)),
+        }
+    }
+}
+
+/// Parsed command-line options, falling back to this crate's historical
+/// defaults (UberDataset.csv, BFS, top 5) wherever a flag is omitted.
+struct Args {
+    input: String,
+    from: Option<String>,
+    to: Option<String>,
+    mode: Mode,
+    coords: Option<String>,
+    top: usize,
+    waypoints: Option<Vec<String>>,
+    free_first: bool,
+    free_last: bool,
+}
+
+impl Args {
+    /// Parse `std::env::args()` into `Args`.
+    fn parse() -> Args {
+        let mut input = "UberDataset.csv".to_string();
+        let mut from = None;
+        let mut to = None;
+        let mut mode = Mode::Bfs;
+        let mut coords = None;
+        let mut top = 5;
+        let mut waypoints = None;
+        let mut free_first = false;
+        let mut free_last = false;
+
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--input" => input = args.next().expect("--input requires a path"),
+                "--from" => from = Some(args.next().expect("--from requires a location")),
+                "--to" => to = Some(args.next().expect("--to requires a location")),
+                "--mode" => {
+                    let value = args.next().expect("--mode requires bfs, dijkstra, or astar");
+                    mode = value.parse().expect("invalid --mode");
+                }
+                "--coords" => coords = Some(args.next().expect("--coords requires a path")),
+                "--top" => {
+                    let value = args.next().expect("--top requires a number");
+                    top = value.parse().expect("--top must be a number");
+                }
+                "--waypoints" => {
+                    let value = args.next().expect("--waypoints requires a comma-separated list of locations");
+                    waypoints = Some(value.split(',').map(|s| s.trim().to_string()).collect());
+                }
+                "--free-first" => free_first = true,
+                "--free-last" => free_last = true,
+                other => input = other.to_string(), // bare positional: treat as input path
+            }
+        }
+
+        Args { input, from, to, mode, coords, top, waypoints, free_first, free_last }
+    }
 }
 
 fn main() {
+    let args = Args::parse();
+
     // Read and filter rides
-    let mut rides = read_file("UberDataset.csv");
-    //dropping unknown locations 
-    rides.retain(|(s, d, _)| {
-        !s.is_empty() && !d.is_empty() &&
-        s != "Unknown Location" && d != "Unknown Location"
+    let mut rides = read_file(&args.input);
+    //dropping unknown locations
+    rides.retain(|r| {
+        !r.start.is_empty() && !r.stop.is_empty() &&
+        r.start != "Unknown Location" && r.stop != "Unknown Location"
     });
     println!("Total rides after filter: {}", rides.len());
 
@@ -167,36 +588,74 @@ fn main() {
     let nodes = unique_nodes(&rides);
     let (adj, locs) = adjacency_list(&rides, &nodes);
 
-    // Top 5 direct routes
-    let top5 = most_frequent_pairs(&rides, 5);
-    println!("\nTop 5 routes:");
-    for ((from, to), count) in &top5 {
+    // Top-N direct routes
+    let top = most_frequent_pairs(&rides, args.top);
+    println!("\nTop {} routes:", args.top);
+    for ((from, to), count) in &top {
         println!("  {} -> {}: {} trips", from, to, count);
     }
 
-    // 4) Popular locations by category
+    // Popular locations by category
     let (personal, business) = popular_hubs(&rides);
     println!("\nPersonal: {}\nBusiness: {}", personal, business);
 
-    // 5) Shortest path for the most frequent route
-    if let Some(((from, to), _count)) = top5.get(0) {
-        if let Some(start_index) = locs.iter().position(|x| x == from) {
-            if let Some(end_index) = locs.iter().position(|x| x == to) {
-                if let Some(path) = shortest_path(&adj, start_index, end_index) {
-                    let names: Vec<&str> = path.iter()
-                        .map(|&idx| locs[idx].as_str())
-                        .collect();
-                    println!("\nShortest {}->{}: {:?}", from, to, names);
+    // Resolve the query: an explicit --from/--to pair, or the most frequent route
+    let query = args.from.clone().zip(args.to.clone())
+        .or_else(|| top.first().map(|((from, to), _)| (from.clone(), to.clone())));
+
+    if let Some((from, to)) = query {
+        let located = (locs.iter().position(|x| *x == from), locs.iter().position(|x| *x == to));
+        if let (Some(start), Some(end)) = located {
+            let resolved: Option<(Vec<usize>, f64)> = match args.mode {
+                Mode::Bfs => shortest_path(&adj, start, end)
+                    .map(|path| { let cost = (path.len() - 1) as f64; (path, cost) }),
+                Mode::Dijkstra => {
+                    let (weighted_adj, _) = adjacency_list_weighted(&rides, &nodes);
+                    shortest_path_weighted(&weighted_adj, start, end)
                 }
+                Mode::Astar => {
+                    let coords = load_coords(args.coords.as_deref().unwrap_or("coords.csv"), &locs);
+                    let geo_adj = adjacency_list_haversine(&adj, &coords);
+                    astar(&geo_adj, &coords, start, end)
+                }
+            };
+            match resolved {
+                Some((path, cost)) => {
+                    let names: Vec<&str> = path.iter().map(|&idx| locs[idx].as_str()).collect();
+                    println!("\n[{:?}] {} -> {}: {:?} (cost {:.2})", args.mode, from, to, names, cost);
+                }
+                None => println!("\n[{:?}] no path from {} to {}", args.mode, from, to),
             }
         }
     }
 
-    // Graph statistics
-    let mut all = Vec::new();
-    for i in 0..adj.len() {
-        all.push(bfs(&adj, i));
+    // Multi-stop tour: visit every --waypoints location, keeping the first
+    // and last stop fixed unless --free-first/--free-last says otherwise.
+    if let Some(names) = &args.waypoints {
+        let resolved: Option<Vec<usize>> = names.iter()
+            .map(|name| locs.iter().position(|x| x == name))
+            .collect();
+        match resolved {
+            Some(waypoint_idxs) => {
+                match best_tour(&adj, &waypoint_idxs, !args.free_first, !args.free_last) {
+                    Some((path, cost)) => {
+                        let tour_names: Vec<&str> = path.iter().map(|&idx| locs[idx].as_str()).collect();
+                        println!("\nTour: {:?} (cost {} hops)", tour_names, cost);
+                    }
+                    None => println!("\nTour: no route connects every waypoint"),
+                }
+            }
+            None => println!("\nTour: one or more --waypoints locations aren't in this dataset"),
+        }
     }
+
+    // Graph statistics: reuse the cached all-pairs hop matrix when it matches
+    // this dataset's locations, otherwise recompute and refresh the cache.
+    let all = stats::load_matrix(DISTANCE_MATRIX_CACHE, &locs).unwrap_or_else(|| {
+        let matrix = stats::all_pairs_bfs(&adj);
+        let _ = stats::save_matrix(DISTANCE_MATRIX_CACHE, &matrix, &locs);
+        matrix
+    });
     let mean = mean_distance(&all);
     let standard_deviation = std_dev(&all, mean);
     let max = max_distance(&all);
@@ -206,13 +665,25 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use stats::bfs;
+
+    fn ride(start: &str, stop: &str, category: Category) -> Ride {
+        Ride {
+            start: start.to_string(),
+            stop: stop.to_string(),
+            category,
+            miles: 1.0,
+            start_date: String::new(),
+            purpose: String::new(),
+        }
+    }
 
-    fn make_rides() -> Vec<(String,String,Category)> {
+    fn make_rides() -> Vec<Ride> {
         vec![
-            ("A".into(), "B".into(), Category::Personal),
-            ("A".into(), "B".into(), Category::Personal),
-            ("B".into(), "C".into(), Category::Business),
-            ("C".into(), "D".into(), Category::Business),
+            ride("A", "B", Category::Personal),
+            ride("A", "B", Category::Personal),
+            ride("B", "C", Category::Business),
+            ride("C", "D", Category::Business),
         ]
     }
 
@@ -226,6 +697,19 @@ mod tests {
             assert_eq!(d[i], Some(0));
         }
     }
+    #[test]
+    fn test_parse_csv_line_handles_quoted_commas() {
+        let fields = parse_csv_line(r#"1/1/2016,"Fort Pierce, FL",Business,"Cary, NC",16.5,"Meeting, planning""#);
+        assert_eq!(fields, vec![
+            "1/1/2016",
+            "Fort Pierce, FL",
+            "Business",
+            "Cary, NC",
+            "16.5",
+            "Meeting, planning",
+        ]);
+    }
+
     #[test]
     fn test_unique_nodes() {
         let rides = make_rides();
@@ -247,6 +731,111 @@ mod tests {
         ]);
     }
 
+    #[test]
+    fn test_shortest_path_weighted_prefers_frequent_route() {
+        // A->B is ridden twice (cheap), A->C->B is ridden once each hop (expensive)
+        let rides = vec![
+            ride("A", "B", Category::Personal),
+            ride("A", "B", Category::Personal),
+            ride("A", "C", Category::Personal),
+            ride("C", "B", Category::Personal),
+        ];
+        let nodes = unique_nodes(&rides);
+        let (adj, locs) = adjacency_list_weighted(&rides, &nodes);
+        let start = locs.iter().position(|x| x == "A").unwrap();
+        let end = locs.iter().position(|x| x == "B").unwrap();
+        let (path, cost) = shortest_path_weighted(&adj, start, end).unwrap();
+        assert_eq!(path, vec![start, end]);
+        assert_eq!(cost, 0.5);
+    }
+
+    #[test]
+    fn test_haversine_zero_for_same_point() {
+        assert_eq!(haversine((42.36, -71.06), (42.36, -71.06)), 0.0);
+    }
+
+    #[test]
+    fn test_astar_matches_bfs_without_coords() {
+        let rides = vec![
+            ride("A", "B", Category::Personal),
+            ride("B", "C", Category::Personal),
+            ride("A", "C", Category::Personal),
+        ];
+        let nodes = unique_nodes(&rides);
+        let (adj, locs) = adjacency_list(&rides, &nodes);
+        // No coordinates available: astar's per-edge cost and heuristic both
+        // fall back to hop-like defaults, so it should agree with plain BFS.
+        let coords: Vec<Option<(f64, f64)>> = vec![None; locs.len()];
+        let geo_adj = adjacency_list_haversine(&adj, &coords);
+        let start = locs.iter().position(|x| x == "A").unwrap();
+        let end = locs.iter().position(|x| x == "C").unwrap();
+        let bfs_path = shortest_path(&adj, start, end).unwrap();
+        let (a_star_path, a_star_cost) = astar(&geo_adj, &coords, start, end).unwrap();
+        assert_eq!(a_star_path, bfs_path);
+        assert_eq!(a_star_cost, (bfs_path.len() - 1) as f64);
+    }
+
+    #[test]
+    fn test_astar_uses_real_coordinates_and_matches_weighted_dijkstra() {
+        // A-B-C is the only route (no direct A-C edge), so the optimal cost
+        // is the sum of the two legs' haversine distances.
+        let rides = vec![
+            ride("A", "B", Category::Personal),
+            ride("B", "C", Category::Personal),
+        ];
+        let nodes = unique_nodes(&rides);
+        let (adj, locs) = adjacency_list(&rides, &nodes);
+        let idx = |name: &str| locs.iter().position(|x| x == name).unwrap();
+
+        let mut coords: Vec<Option<(f64, f64)>> = vec![None; locs.len()];
+        coords[idx("A")] = Some((40.0, -75.0));
+        coords[idx("B")] = Some((40.5, -75.5));
+        coords[idx("C")] = Some((41.0, -76.0));
+
+        let geo_adj = adjacency_list_haversine(&adj, &coords);
+        let start = idx("A");
+        let end = idx("C");
+
+        let expected_cost = haversine(coords[idx("A")].unwrap(), coords[idx("B")].unwrap())
+            + haversine(coords[idx("B")].unwrap(), coords[idx("C")].unwrap());
+
+        let (dijkstra_path, dijkstra_cost) = shortest_path_weighted(&geo_adj, start, end).unwrap();
+        let (a_star_path, a_star_cost) = astar(&geo_adj, &coords, start, end).unwrap();
+
+        assert_eq!(a_star_path, dijkstra_path);
+        assert!((a_star_cost - expected_cost).abs() < 1e-9);
+        assert!((a_star_cost - dijkstra_cost).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_best_tour_pins_endpoints_and_orders_middle() {
+        // Line graph A-B-C-D (edges both ways); visiting B and C between pinned
+        // A and D should prefer the order that never backtracks.
+        let rides = vec![
+            ride("A", "B", Category::Personal),
+            ride("B", "A", Category::Personal),
+            ride("B", "C", Category::Personal),
+            ride("C", "B", Category::Personal),
+            ride("C", "D", Category::Personal),
+            ride("D", "C", Category::Personal),
+        ];
+        let nodes = unique_nodes(&rides);
+        let (adj, locs) = adjacency_list(&rides, &nodes);
+        let idx = |name: &str| locs.iter().position(|x| x == name).unwrap();
+        let waypoints = vec![idx("A"), idx("C"), idx("B"), idx("D")];
+        let (path, cost) = best_tour(&adj, &waypoints, true, true).unwrap();
+        assert_eq!(path, vec![idx("A"), idx("B"), idx("C"), idx("D")]);
+        assert_eq!(cost, 3);
+    }
+
+    #[test]
+    fn test_mode_from_str() {
+        assert_eq!("bfs".parse::<Mode>(), Ok(Mode::Bfs));
+        assert_eq!("Dijkstra".parse::<Mode>(), Ok(Mode::Dijkstra));
+        assert_eq!("ASTAR".parse::<Mode>(), Ok(Mode::Astar));
+        assert!("teleport".parse::<Mode>().is_err());
+    }
+
     #[test]
     fn test_max_distance() {
         let rides = read_file("test.txt");
@@ -0,0 +1,203 @@
+use std::collections::VecDeque;
+use std::collections::hash_map::DefaultHasher;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufWriter, Read, Write};
+use std::sync::Arc;
+use std::thread;
+
+/// BFS hop distances from `start` to every other node, `None` if unreachable.
+pub fn bfs(adj: &[Vec<usize>], start: usize) -> Vec<Option<u32>> {
+    let mut dist = vec![None; adj.len()];
+    let mut queue = VecDeque::new();
+    dist[start] = Some(0);
+    queue.push_back(start);
+
+    while let Some(u) = queue.pop_front() {
+        for &v in &adj[u] {
+            if dist[v].is_none() {
+                dist[v] = Some(dist[u].unwrap() + 1);
+                queue.push_back(v);
+            }
+        }
+    }
+    dist
+}
+
+/// Mean hop distance across all reachable node pairs.
+pub fn mean_distance(all: &[Vec<Option<u32>>]) -> f64 {
+    let (sum, count) = all.iter().flatten().filter_map(|d| *d)
+        .fold((0u64, 0u64), |(sum, count), d| (sum + d as u64, count + 1));
+    if count == 0 { 0.0 } else { sum as f64 / count as f64 }
+}
+
+/// Standard deviation of hop distances around `mean`.
+pub fn std_dev(all: &[Vec<Option<u32>>], mean: f64) -> f64 {
+    let values: Vec<f64> = all.iter().flatten().filter_map(|d| *d).map(|d| d as f64).collect();
+    if values.is_empty() {
+        return 0.0;
+    }
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64;
+    variance.sqrt()
+}
+
+/// Largest finite hop distance across all node pairs.
+pub fn max_distance(all: &[Vec<Option<u32>>]) -> u32 {
+    all.iter().flatten().filter_map(|d| *d).max().unwrap_or(0)
+}
+
+/// All-pairs hop distance matrix: one BFS sweep per source node.
+pub fn precompute(adj: &[Vec<usize>]) -> Vec<Vec<Option<u32>>> {
+    (0..adj.len()).map(|i| bfs(adj, i)).collect()
+}
+
+/// Parallel counterpart to `precompute`: chunks source nodes across a pool of
+/// threads sized to the available parallelism and assembles each source's
+/// distance row back in source order. `adj` is read-only during the sweep,
+/// so sharing it via `Arc` needs no locking.
+pub fn all_pairs_bfs(adj: &[Vec<usize>]) -> Vec<Vec<Option<u32>>> {
+    let n = adj.len();
+    if n == 0 {
+        return Vec::new();
+    }
+    let worker_count = thread::available_parallelism().map(|p| p.get()).unwrap_or(1).min(n);
+    if worker_count <= 1 {
+        return precompute(adj);
+    }
+
+    let adj = Arc::new(adj.to_vec());
+    let chunk_size = n.div_ceil(worker_count);
+    let handles: Vec<_> = (0..n).step_by(chunk_size)
+        .map(|chunk_start| {
+            let chunk_end = (chunk_start + chunk_size).min(n);
+            let adj = Arc::clone(&adj);
+            thread::spawn(move || -> Vec<Vec<Option<u32>>> {
+                (chunk_start..chunk_end).map(|i| bfs(&adj, i)).collect()
+            })
+        })
+        .collect();
+
+    handles.into_iter()
+        .flat_map(|handle| handle.join().expect("worker thread panicked"))
+        .collect()
+}
+
+/// Stable fingerprint of the location list, so a cached matrix can be
+/// rejected if the dataset (and therefore the node ordering) has changed.
+fn fingerprint(locations: &[String]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    locations.hash(&mut hasher);
+    hasher.finish()
+}
+
+const MATRIX_MAGIC: u32 = 0x4D545831; // "MTX1"
+
+/// Write `matrix` to `path` in a compact binary form: a header with the node
+/// count and a fingerprint of `locations` (to detect a stale cache), followed
+/// by length-prefixed rows of fixed-width `i64` cells (`-1` marks unreachable).
+pub fn save_matrix(path: &str, matrix: &[Vec<Option<u32>>], locations: &[String]) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    out.write_all(&MATRIX_MAGIC.to_le_bytes())?;
+    out.write_all(&(matrix.len() as u64).to_le_bytes())?;
+    out.write_all(&fingerprint(locations).to_le_bytes())?;
+    for row in matrix {
+        out.write_all(&(row.len() as u64).to_le_bytes())?;
+        for cell in row {
+            let value: i64 = cell.map(|d| d as i64).unwrap_or(-1);
+            out.write_all(&value.to_le_bytes())?;
+        }
+    }
+    out.flush()
+}
+
+/// Load a matrix saved by `save_matrix`, returning `None` if the file is
+/// missing, malformed, or its fingerprint doesn't match `locations` (i.e.
+/// the cache is stale and the caller should recompute).
+pub fn load_matrix(path: &str, locations: &[String]) -> Option<Vec<Vec<Option<u32>>>> {
+    let mut file = File::open(path).ok()?;
+    let mut buf = Vec::new();
+    file.read_to_end(&mut buf).ok()?;
+    let mut cursor = 0usize;
+
+    let read_u32 = |buf: &[u8], cursor: &mut usize| -> Option<u32> {
+        let bytes: [u8; 4] = buf.get(*cursor..*cursor + 4)?.try_into().ok()?;
+        *cursor += 4;
+        Some(u32::from_le_bytes(bytes))
+    };
+    let read_u64 = |buf: &[u8], cursor: &mut usize| -> Option<u64> {
+        let bytes: [u8; 8] = buf.get(*cursor..*cursor + 8)?.try_into().ok()?;
+        *cursor += 8;
+        Some(u64::from_le_bytes(bytes))
+    };
+    let read_i64 = |buf: &[u8], cursor: &mut usize| -> Option<i64> {
+        let bytes: [u8; 8] = buf.get(*cursor..*cursor + 8)?.try_into().ok()?;
+        *cursor += 8;
+        Some(i64::from_le_bytes(bytes))
+    };
+
+    if read_u32(&buf, &mut cursor)? != MATRIX_MAGIC {
+        return None;
+    }
+    let node_count = read_u64(&buf, &mut cursor)? as usize;
+    let cached_fingerprint = read_u64(&buf, &mut cursor)?;
+    if node_count != locations.len() || cached_fingerprint != fingerprint(locations) {
+        return None;
+    }
+
+    let mut matrix = Vec::with_capacity(node_count);
+    for _ in 0..node_count {
+        let row_len = read_u64(&buf, &mut cursor)? as usize;
+        let mut row = Vec::with_capacity(row_len);
+        for _ in 0..row_len {
+            let value = read_i64(&buf, &mut cursor)?;
+            row.push(if value < 0 { None } else { Some(value as u32) });
+        }
+        matrix.push(row);
+    }
+    Some(matrix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_all_pairs_bfs_matches_precompute() {
+        let adj = vec![vec![1, 2], vec![2], vec![0], vec![]];
+        assert_eq!(all_pairs_bfs(&adj), precompute(&adj));
+    }
+
+    #[test]
+    fn test_precompute_matches_bfs() {
+        let adj = vec![vec![1], vec![2], vec![]];
+        let matrix = precompute(&adj);
+        assert_eq!(matrix[0], bfs(&adj, 0));
+        assert_eq!(matrix[0][2], Some(2));
+    }
+
+    #[test]
+    fn test_save_and_load_matrix_round_trips() {
+        let adj = vec![vec![1], vec![0]];
+        let matrix = precompute(&adj);
+        let locations = vec!["A".to_string(), "B".to_string()];
+        let path = std::env::temp_dir().join("stats_test_matrix.bin");
+        let path_str = path.to_str().unwrap();
+        save_matrix(path_str, &matrix, &locations).unwrap();
+        let loaded = load_matrix(path_str, &locations).unwrap();
+        assert_eq!(loaded, matrix);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_load_matrix_rejects_stale_fingerprint() {
+        let adj = vec![vec![1], vec![0]];
+        let matrix = precompute(&adj);
+        let locations = vec!["A".to_string(), "B".to_string()];
+        let path = std::env::temp_dir().join("stats_test_matrix_stale.bin");
+        let path_str = path.to_str().unwrap();
+        save_matrix(path_str, &matrix, &locations).unwrap();
+        let other_locations = vec!["A".to_string(), "C".to_string()];
+        assert!(load_matrix(path_str, &other_locations).is_none());
+        std::fs::remove_file(path).unwrap();
+    }
+}